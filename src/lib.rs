@@ -33,14 +33,15 @@
 /// ```
 /// This will produce an error similar to this one:
 /// ```plain
-/// error: unexpected end of macro invocation
-///    --> src\lib.rs:286:16
-///     |
-/// 234 |         macro_rules! $macro {
-///     |         ------------------- when calling this macro
-/// ...
-/// 286 |     #[variant(c)]
-///     |                ^ missing tokens in macro arguments
+/// error: missing substitution for parameter `b`, which has no default
+///   --> src/main.rs:2:1
+///    |
+/// 2  | / variants!(
+/// 3  | |     #[dollar($)]
+/// 4  | |     #[variant(c)]
+/// 5  | |     macro test(a, b) {}
+/// 6  | | );
+///    | |_^
 /// ```
 ///
 /// When, instead, there are more substitutions than needed, no error will be generated and the extra ones
@@ -54,6 +55,27 @@
 /// );
 /// ```
 ///
+/// A `<param>` can also declare a default with `<param> = <default>`, where `<default>` is a
+/// single token tree. If a variant has fewer substitutions than parameters, the missing trailing
+/// ones are taken from their parameter's default instead of producing the error above; parameters
+/// without a default remain mandatory:
+/// ```
+/// # use variants::variants;
+/// variants!(
+///     #[dollar($)]
+///     #[variant(double)]
+///     #[variant(triple, 3)]
+///     macro multiply_by(name, factor = 2) {
+///         fn $name(x: usize) -> usize {
+///             x * $factor
+///         }
+///     }
+/// );
+///
+/// assert_eq!(double(4), 8);
+/// assert_eq!(triple(4), 12);
+/// ```
+///
 /// The macro defined with the given name can be used to choose which code to execute for which variant
 ///
 /// The syntax is `<macro>!(<sub> | <sub> | ... : { <code> }, <sub> : { <code> }, ...)`
@@ -69,6 +91,34 @@
 /// If a substitution that doesn't exist in any variant is added (that is not `_`), the macro will
 /// generate a compile time error.
 ///
+/// A `<sub>` can also be negated by prefixing it with `!`, which flips the match: `!sub` keeps
+/// the code in every variant whose substitution is *not* `sub`, and drops it in the variant
+/// that is. Negated keys can be combined with `|`, as in `!a | !b : { <code> }`, in which case
+/// the code is kept only in the variants that match none of them (equivalent to `!(a | b)`).
+/// Negated and regular keys can be freely mixed across the comma-separated list, so writing a
+/// default case for every variant but one is as simple as:
+/// ```
+/// # use variants::variants;
+/// variants!(
+///     #[dollar($)]
+///     #[variant(add_one_ref, [&usize])]
+///     #[variant(add_one_mut, [&mut usize])]
+///     macro refmut2(name, ty) {
+///         fn $name(param: $ty) -> usize {
+///             let out = *param + 1;
+///             refmut2!(!add_one_ref: { *param += 1; });
+///             out
+///         }
+///     }
+/// );
+///
+/// let mut test = 0;
+/// assert_eq!(add_one_ref(&test), 1);
+/// assert_eq!(test, 0);
+/// assert_eq!(add_one_mut(&mut test), 1);
+/// assert_eq!(test, 1);
+/// ```
+///
 /// ## What's the deal with the dollar?
 ///
 /// The generated code will be expanded inside a macro definition, so that the `<param>`s can be
@@ -85,6 +135,57 @@
 /// By doing so, every occurrence of `$name` will be replaced with the dollar sign.
 /// An example of how to use it it's shown later.
 ///
+/// ## Variant index
+///
+/// An optional `#[index(<name>)]` attribute can be added right after `#[dollar(...)]` to bind
+/// `$name` to the 0-based position of the variant currently being expanded, as a `usize`
+/// expression. This is useful whenever the generated code needs to know its own ordinal, for
+/// example to derive enum discriminants, array slots or bit flags:
+/// ```
+/// # use variants::variants;
+/// variants!(
+///     #[dollar($)]
+///     #[index(i)]
+///     #[variant(Red)]
+///     #[variant(Green)]
+///     #[variant(Blue)]
+///     macro color_bit(name) {
+///         const $name: u8 = 1 << $i;
+///     }
+/// );
+///
+/// assert_eq!(Red, 0b001);
+/// assert_eq!(Green, 0b010);
+/// assert_eq!(Blue, 0b100);
+/// ```
+///
+/// ## Multi-token substitutions
+///
+/// A `<substitution>` normally has to be a single token-tree, which is why a multi-token
+/// type such as `&mut usize` has to be wrapped in a group to be used as one; written as
+/// `(&mut usize)` it keeps its parenthesis in the output. Wrapping a substitution in `[...]` instead asks for those
+/// brackets to be stripped again at the point where `$param` is used, so the tokens inside
+/// are spliced in directly with no delimiter left behind. Because of this, `[...]` is no longer
+/// available as a way to substitute a literal bracketed token-tree (an array type, for example)
+/// with its brackets kept; wrap it in parenthesis instead if that's what's needed:
+/// ```
+/// # use variants::variants;
+/// variants!(
+///     #[dollar($)]
+///     #[variant(add_one_ref, [&usize])]
+///     #[variant(add_one_mut, [&mut usize])]
+///     macro refmut3(name, ty) {
+///         fn $name(param: $ty) -> usize {
+///             *param + 1
+///         }
+///     }
+/// );
+///
+/// let mut test = 0;
+/// assert_eq!(add_one_ref(&test), 1);
+/// assert_eq!(add_one_mut(&mut test), 1);
+/// ```
+///
 /// # Example
 ///
 /// Let's say you have a type that wraps a number and you have to overload the
@@ -130,8 +231,8 @@
 /// # use variants::variants;
 /// variants!(
 ///     #[dollar($)]
-///     #[variant(add_one_ref, (&usize))]
-///     #[variant(add_one_mut, (&mut usize))]
+///     #[variant(add_one_ref, [&usize])]
+///     #[variant(add_one_mut, [&mut usize])]
 ///     macro refmut(name, ty) {
 ///         fn $name(param: $ty) -> usize {
 ///             let out = *param + 1;
@@ -162,76 +263,362 @@
 /// }
 /// ```
 ///
-/// ### Note
-///
-/// Wrapping the type in parenthesis is needed as a substitution must be a single token-tree
-/// but this will produce the warning "unnecessary parentheses around type" so if this
-/// is not wanted two solutions can be used:
-/// 1) Add `#[allow(unused_parens)]` before the `fn $name`
-/// 2) Use a `remove_parens!` macro, which simply removes the parenthesis;\
-///    Such a macro can be written like this:
-///    ```ignore
-///    macro_rules! remove_parens { (($($t:tt)*)) => {$($t)*} }
-///    ```
+/// Note that `[&mut usize]` above is a bracket-group substitution (see "Multi-token
+/// substitutions"); writing it as `(&mut usize)` instead would also work, but would produce
+/// the "unnecessary parentheses around type" warning.
 #[macro_export]
 macro_rules! variants {
     // NOTE: what is $d?
     // $d must be the dollar sign (`$`) and it's needed to generate macros that take parameters
     // because the dollar sign cannot be used inside a macro definition
+    (
+        #[dollar($d:tt $(as $dollar:ident)?)]
+        #[index($index:ident)]
+        #[variant($($sub:tt),+)]
+        $(#[variant($($other_sub:tt),+)])*
+        macro $macro:ident($($param:ident $(= $default:tt)?),+)
+        {$($i:tt)*}
+    ) => {
+        // NOTE: #[accum(...)] starts empty here and grows by one `()` per variant already
+        // expanded, see the arms below for how it's turned into the variant's index
+        // NOTE: #[keys(...)] is the flat union of every variant's substitutions, computed once
+        // up front (it doesn't grow like #[accum], it's the same full set at every recursion
+        // step) so each variant's selector macro can tell a key that belongs to some other
+        // variant apart from one that isn't a substitution anywhere at all
+        $crate::variants!{
+            #[dollar($d $(as $dollar)?)]
+            #[index($index)]
+            #[accum()]
+            #[keys($($sub),+ $(, $($other_sub),+)*)]
+            #[variant($($sub),+)]
+            $(#[variant($($other_sub),+)])*
+            macro $macro($($param $(= $default)?),+)
+            {$($i)*}
+        }
+    };
     (
         #[dollar($d:tt $(as $dollar:ident)?)]
         #[variant($($sub:tt),+)]
         $(#[variant($($other_sub:tt),+)])*
-        macro $macro:ident($($param:ident),+)
+        macro $macro:ident($($param:ident $(= $default:tt)?),+)
+        {$($i:tt)*}
+    ) => {
+        // NOTE: no #[index(...)] was given, so a never-referenced placeholder name is used
+        // instead, the real logic lives in the #[accum(...)]-bearing arms below
+        $crate::variants!{
+            #[dollar($d $(as $dollar)?)]
+            #[index(__variants_unused_index)]
+            #[accum()]
+            #[keys($($sub),+ $(, $($other_sub),+)*)]
+            #[variant($($sub),+)]
+            $(#[variant($($other_sub),+)])*
+            macro $macro($($param $(= $default)?),+)
+            {$($i)*}
+        }
+    };
+    (
+        #[dollar($d:tt $(as $dollar:ident)?)]
+        $(#[index($index:ident)])?
+        macro $macro:ident($($param:ident $(= $default:tt)?),+)
+        {$($i:tt)*}
+    ) => {};
+    (
+        #[dollar($d:tt $(as $dollar:ident)?)]
+        #[index($index:ident)]
+        #[accum($($accum:tt)*)]
+        #[keys($($keys:tt),+)]
+        #[variant($($sub:tt),+)]
+        $(#[variant($($other_sub:tt),+)])*
+        macro $macro:ident($($param:ident $(= $default:tt)?),+)
         {$($i:tt)*}
     ) => {
         // NOTE: here $d is the same as $
         macro_rules! $macro {
-            // Same as: $sub $(| $_:tt)* : { $($t:tt)* } $(, $($__:tt)|+ : { $($___:tt)* })* $(,)?
-            $(($sub $d (| $d _:tt)* : { $d ($d t:tt)* } $d (, $d ($d __:tt)|+ : { $d ($d ___:tt)* })* $d (,)?) => {
+            // NOTE: recursing on a still-unevaluated remainder can leave a leading comma in
+            // front of it (the comma that used to separate it from the group just handled);
+            // strip it before trying to match the remainder against the arms below
+            (, $d ($d rest:tt)*) => {
+                $macro!{$d ($d rest)*}
+            };
+            // Same as: $sub $(| $_:tt)* : { $($t:tt)* } $($rest:tt)*
+            $(($sub $d (| $d _:tt)* : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
                 // Same as: $($t)*
                 $d ($d t)*
             };)+
-            // Same as: _ $(| $_:tt)* : { $($t:tt)* } $(, $($__:tt)|+ : { $($___:tt)* })* $(,)?
-            (_ $d (| $d _:tt)* : { $d ($d t:tt)* } $d (, $d ($d __:tt)|+ : { $d ($d ___:tt)* })* $d (,)?) => {
+            // Same as: _ $(| $_:tt)* : { $($t:tt)* } $($rest:tt)*
+            (_ $d (| $d _:tt)* : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
                 // Same as: $($t)*
                 $d ($d t)*
             };
-            // Same as: $sv:tt : { $($__:tt)* } $(, $($v:tt)|+ : { $($t:tt)* })* $(,)?
-            ($d sv:tt : { $d ($d __:tt)* } $d (, $d ($d v:tt)|+ : { $d ($d t:tt)* })* $d (,)?) => {
-                // Same as: $macro!{$($v $(| $va)* : { $($t)* }),*}
-                $macro!{$d ($d ($d v)|+ : { $d ($d t)* }),*}
+            // Same as: !$sub $(| !$_:tt)* : { $($__:tt)* } $($rest:tt)*
+            // NOTE: a negated key excludes the variant it names, so a match here is handled
+            // exactly like a non-matching key: drop this group and keep checking the rest
+            $((! $sub $d (| ! $d _:tt)* : { $d ($d __:tt)* } $d ($d rest:tt)*) => {
+                $macro!{$d ($d rest)*}
+            };)+
+            // Same as: !$keys : { $($t:tt)* } $($rest:tt)*
+            // NOTE: a negated key naming a substitution that belongs to some *other* variant
+            // (not this one, those arms are above and win first) doesn't exclude this variant:
+            // keep the code. This has to come before the catch-all error arm below, so that only
+            // keys matching no variant's substitution at all fall through to it.
+            // NOTE: restricted to a lone key, with no trailing `| !...` chain: a chain needs
+            // every element checked against this variant's own substitution before "keep" can
+            // be concluded, and matching (and thus deciding) on just the first one here would
+            // ignore the rest of the chain. Chains fall through instead to the generic
+            // strip-and-recurse arm further down, which peels one key off at a time and re-runs
+            // every arm above (including the "own substitution" ones) on what's left
+            $((! $keys : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
+                $d ($d t)*
+            };)+
+            // Same as: $sv:tt : { $($__:tt)* } $($rest:tt)*
+            ($d sv:tt : { $d ($d __:tt)* } $d ($d rest:tt)*) => {
+                $macro!{$d ($d rest)*}
+            };
+            // Same as: $_:tt $(| $v:tt)+ : { $($t:tt)* } $($rest:tt)*
+            ($d _:tt $d (| $d v:tt)+ : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
+                // Same as: $macro!{$($v)|+ : { $($t)* } $($rest)*}
+                $macro!{$d ($d v)|+ : { $d ($d t)* } $d ($d rest)*}
             };
-            // Same as: $_:tt $(| $v:tt)+ : { $($t:tt)* } $(, $($ov:tt)|+ : { $($ot:tt)* })* $(,)?
-            ($d _:tt $d (| $d v:tt)+ : { $d ($d t:tt)* } $d (, $d ($d ov:tt)|+ : { $d ($d ot:tt)* })* $d (,)?) => {
-                // Same as: $macro!{$($v)|+ : { $($t)* }, $($($ov)|+ : { $($ot)* }),*}
-                $macro!{$d ($d v)|+ : { $d ($d t)* }, $d ($d ($d ov)|+ : { $d ($d ot)* }),*}
+            // Same as: !$sv:tt : { $($t:tt)* } $($rest:tt)*
+            // NOTE: every substitution that actually belongs to some variant (this one or any
+            // other) was already matched by one of the two repetitions above; reaching this arm
+            // means $sv isn't a substitution anywhere, so negating it can never do anything
+            // useful and is rejected instead of silently being treated as "always kept"
+            (! $d sv:tt : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
+                compile_error!(concat!(
+                    "`!", stringify!($d sv), "` does not name a substitution of any variant"
+                ))
+            };
+            // Same as: !$_:tt $(| !$v:tt)+ : { $($t:tt)* } $($rest:tt)*
+            // NOTE: the first negated key in the list didn't exclude this variant either,
+            // drop it and keep checking whether one of the remaining ones does
+            (! $d _:tt $d (| ! $d v:tt)+ : { $d ($d t:tt)* } $d ($d rest:tt)*) => {
+                // Same as: $macro!{!$($v)|+ : { $($t)* } $($rest)*}
+                $macro!{! $d ($d v)|+ : { $d ($d t)* } $d ($d rest)*}
             };
             // Same as: $($v)|+ : $($t:tt)*
             ($d ($d v:tt)|+ : $d ($d t:tt)*) => {
                 // Same as: $macro!{$($v)|+ : { $($t)* }}
                 $macro!{$d ($d v)|+ : { $d ($d t)* }}
             };
+            // Same as: !$($v)|+ : $($t:tt)*
+            (! $d ($d v:tt)|+ : $d ($d t:tt)*) => {
+                // Same as: $macro!{!$($v)|+ : { $($t)* }}
+                $macro!{! $d ($d v)|+ : { $d ($d t)* }}
+            };
             () => {};
             // NOTE: why not doing everything inside the first matcher?
             // That's because if that was done an extra variable called $d will be avaiable inside the given code
             // which is not wanted, as the only "doller-meta-variable" should be $dollar
-            (@$([$d $dollar:tt])?expand $($d $param:tt)+ $d ($d _:tt)*) => { $($i)* };
+            // NOTE: params and the index are passed in their own bracket groups so that the
+            // trailing catch-all used to ignore extra substitutions can't also swallow the index
+            (@$([$d $dollar:tt])?expand [$($d $param:tt)+ $d ($d _:tt)*] [$d $index:tt]) => { $($i)* };
             // NOTE: this catches the cases when $dollar is not defined, as the [$] is always set
-            (@[$d _:tt]expand $($d $param:tt)+ $d ($d __:tt)*) => { $($i)* };
+            (@[$d _:tt]expand [$($d $param:tt)+ $d ($d __:tt)*] [$d $index:tt]) => { $($i)* };
+            // NOTE: only reached when at least one substitution went through a `[...]` group
+            // (see __variants_bind): $param still splices in with its brackets, so the body is
+            // routed through __variants_unwrap to strip them back out. Variants with no
+            // bracket-group substitutions never hit this path, since walking the whole body
+            // token-by-token would otherwise risk the macro recursion limit for no reason
+            (@$([$d $dollar:tt])?expand_bracketed [$($d $param:tt)+ $d ($d _:tt)*] [$d $index:tt]) => {
+                $crate::__variants_unwrap!{$($i)*}
+            };
+            (@[$d _:tt]expand_bracketed [$($d $param:tt)+ $d ($d __:tt)*] [$d $index:tt]) => {
+                $crate::__variants_unwrap!{$($i)*}
+            };
         }
-        $macro!{@[$d]expand $($sub)+}
+        // NOTE: the accumulator built so far is exactly the 0-based index of this variant;
+        // going through the slice `len` rather than `[$($accum)*].len()` keeps the element
+        // type of the array from being ambiguous when the accumulator is still empty
+        // NOTE: this variant's substitutions may run short of the declared parameters, so
+        // they're padded out with the parameters' own defaults before being handed to $macro
+        $crate::__variants_bind!{$d $macro [(<[()]>::len(&[$($accum)*]))] [$($param $(= $default)?),+] [$($sub)+] [] []}
 
         $crate::variants!{
             #[dollar($d $(as $dollar)?)]
+            #[index($index)]
+            #[accum($($accum)* (),)]
+            #[keys($($keys),+)]
             $(#[variant($($other_sub),+)])*
-            macro $macro($($param),+)
+            macro $macro($($param $(= $default)?),+)
             {$($i)*}
         }
     };
     (
         #[dollar($d:tt $(as $dollar:ident)?)]
-        macro $macro:ident($($param:ident),+)
+        $(#[index($index:ident)])?
+        #[accum($($accum:tt)*)]
+        #[keys($($keys:tt),+)]
+        macro $macro:ident($($param:ident $(= $default:tt)?),+)
         {$($i:tt)*}
     ) => {};
 }
+
+// NOTE: this is an implementation detail of `variants!`, not meant to be used directly: it pads
+// out a variant's substitutions with the declared parameter defaults (for any trailing parameter
+// left without one) and then hands the resulting flat list over to `$macro`'s `@expand` arms.
+// Alongside the bound list it threads a second accumulator that collects one throwaway token per
+// bracket-group substitution bound so far; its emptiness at the end decides whether the body
+// needs the (token-walking, so non-free) __variants_unwrap pass at all
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __variants_bind {
+    // No parameters are left and no bracket-group substitution was ever bound: the flat list can
+    // be handed to `$macro` for a plain expansion, with no unwrap pass needed
+    ($dollar:tt $macro:ident [$index:tt] [] [$($extra:tt)*] [$($bound:tt)*] []) => {
+        $macro!{@[$dollar]expand [$($bound)*] [$index]}
+    };
+    // No parameters are left, but at least one substitution came through a `[...]` group: route
+    // the expansion through __variants_unwrap so those brackets get stripped back out
+    ($dollar:tt $macro:ident [$index:tt] [] [$($extra:tt)*] [$($bound:tt)*] [$($bracket:tt)+]) => {
+        $macro!{@[$dollar]expand_bracketed [$($bound)*] [$index]}
+    };
+    // A bracket-delimited substitution is still available: bind it same as any other, but tag
+    // its contents with the marker __variants_unwrap looks for, and record that a bracket group
+    // was seen
+    (
+        $dollar:tt $macro:ident [$index:tt]
+        [$param:ident $(= $default:tt)? $(, $($rest_param:tt)*)?]
+        [[$($sub_toks:tt)*] $($rest_sub:tt)*]
+        [$($bound:tt)*]
+        [$($bracket:tt)*]
+    ) => {
+        $crate::__variants_bind!{
+            $dollar $macro [$index]
+            [$($($rest_param)*)?]
+            [$($rest_sub)*]
+            [$($bound)* [__variants_bracket__ $($sub_toks)*]]
+            [$($bracket)* x]
+        }
+    };
+    // A substitution is still available: bind it to the current parameter and move on
+    (
+        $dollar:tt $macro:ident [$index:tt]
+        [$param:ident $(= $default:tt)? $(, $($rest_param:tt)*)?]
+        [$sub:tt $($rest_sub:tt)*]
+        [$($bound:tt)*]
+        [$($bracket:tt)*]
+    ) => {
+        $crate::__variants_bind!{
+            $dollar $macro [$index]
+            [$($($rest_param)*)?]
+            [$($rest_sub)*]
+            [$($bound)* $sub]
+            [$($bracket)*]
+        }
+    };
+    // No substitution is left for the current parameter, and its declared default is itself a
+    // bracket group: fall back to it the same way an explicit bracket-group substitution would be
+    // bound, so defaulted parameters get unwrapped too (this has to come before the plain default
+    // arm below, as it's the more specific of the two)
+    (
+        $dollar:tt $macro:ident [$index:tt]
+        [$param:ident = [$($default_toks:tt)*] $(, $($rest_param:tt)*)?]
+        []
+        [$($bound:tt)*]
+        [$($bracket:tt)*]
+    ) => {
+        $crate::__variants_bind!{
+            $dollar $macro [$index]
+            [$($($rest_param)*)?]
+            []
+            [$($bound)* [__variants_bracket__ $($default_toks)*]]
+            [$($bracket)* x]
+        }
+    };
+    // No substitution is left for the current parameter: fall back to its declared default
+    (
+        $dollar:tt $macro:ident [$index:tt]
+        [$param:ident = $default:tt $(, $($rest_param:tt)*)?]
+        []
+        [$($bound:tt)*]
+        [$($bracket:tt)*]
+    ) => {
+        $crate::__variants_bind!{
+            $dollar $macro [$index]
+            [$($($rest_param)*)?]
+            []
+            [$($bound)* $default]
+            [$($bracket)*]
+        }
+    };
+    // No substitution and no default are left for the current parameter: every arm above that
+    // could have consumed one already had its chance, so this is a genuine error in the
+    // `#[variant(...)]` that's missing it. Reporting it here, instead of just letting this
+    // invocation fail to match any arm, avoids leaking this macro's own name and internal `[...]`
+    // bracket-group syntax into the user-facing error
+    (
+        $dollar:tt $macro:ident [$index:tt]
+        [$param:ident $(, $($rest_param:tt)*)?]
+        []
+        [$($bound:tt)*]
+        [$($bracket:tt)*]
+    ) => {
+        compile_error!(concat!(
+            "missing substitution for parameter `", stringify!($param), "`, which has no default"
+        ));
+    };
+}
+
+// NOTE: this is an implementation detail of `variants!`, not meant to be used directly: once a
+// variant's body has had all its `$param`s substituted in (brackets and all, see
+// __variants_bind), this walks the resulting tokens and, wherever it finds a
+// `[__variants_bracket__ ...]` group, splices its contents back in without the surrounding
+// brackets, so a `[...]`-wrapped substitution reads as if it had never been delimited at all
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __variants_unwrap {
+    ($($t:tt)*) => {
+        $crate::__variants_unwrap_impl!{[done] [] $($t)*}
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __variants_unwrap_impl {
+    // nothing left to process and nothing to return to: the accumulated output is the result
+    ([done] [$($out:tt)*]) => {
+        $($out)*
+    };
+    // a marked bracket group has been fully unwrapped: splice it into the enclosing output with
+    // no delimiter of its own, then keep going with whatever followed it
+    ([resume_bracket_marker [$($out:tt)*] [$($rest:tt)*] $($cont:tt)*] [$($inner:tt)*]) => {
+        $crate::__variants_unwrap_impl!{[$($cont)*] [$($out)* $($inner)*] $($rest)*}
+    };
+    // an ordinary group has been fully processed: close it back up with its own delimiter and
+    // keep going with whatever followed it
+    ([resume_paren [$($out:tt)*] [$($rest:tt)*] $($cont:tt)*] [$($inner:tt)*]) => {
+        $crate::__variants_unwrap_impl!{[$($cont)*] [$($out)* ($($inner)*)] $($rest)*}
+    };
+    ([resume_bracket [$($out:tt)*] [$($rest:tt)*] $($cont:tt)*] [$($inner:tt)*]) => {
+        $crate::__variants_unwrap_impl!{[$($cont)*] [$($out)* [$($inner)*]] $($rest)*}
+    };
+    ([resume_brace [$($out:tt)*] [$($rest:tt)*] $($cont:tt)*] [$($inner:tt)*]) => {
+        $crate::__variants_unwrap_impl!{[$($cont)*] [$($out)* {$($inner)*}] $($rest)*}
+    };
+    // a marked bracket group: remember what to do once its contents are processed, then recurse
+    // into them (this has to come before the plain `[...]` arm below, as it's the more specific
+    // of the two)
+    // NOTE: like every other `__variants_*` name, `__variants_bracket__` is reserved for this
+    // crate's own use; a variant body that independently happens to contain a `[...]` group
+    // starting with that exact identifier would have it stripped by mistake, same as it would
+    // for any other internal marker token
+    ([$($cont:tt)*] [$($out:tt)*] [__variants_bracket__ $($inner:tt)*] $($rest:tt)*) => {
+        $crate::__variants_unwrap_impl!{[resume_bracket_marker [$($out)*] [$($rest)*] $($cont)*] [] $($inner)*}
+    };
+    // an ordinary group: recurse into its contents, remembering to close it back up and resume
+    // the rest of the current level once that's done
+    ([$($cont:tt)*] [$($out:tt)*] ($($inner:tt)*) $($rest:tt)*) => {
+        $crate::__variants_unwrap_impl!{[resume_paren [$($out)*] [$($rest)*] $($cont)*] [] $($inner)*}
+    };
+    ([$($cont:tt)*] [$($out:tt)*] [$($inner:tt)*] $($rest:tt)*) => {
+        $crate::__variants_unwrap_impl!{[resume_bracket [$($out)*] [$($rest)*] $($cont)*] [] $($inner)*}
+    };
+    ([$($cont:tt)*] [$($out:tt)*] {$($inner:tt)*} $($rest:tt)*) => {
+        $crate::__variants_unwrap_impl!{[resume_brace [$($out)*] [$($rest)*] $($cont)*] [] $($inner)*}
+    };
+    // any other single token: copy it through unchanged
+    ([$($cont:tt)*] [$($out:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::__variants_unwrap_impl!{[$($cont)*] [$($out)* $t] $($rest)*}
+    };
+}